@@ -1,11 +1,31 @@
+use crate::matcher::Matcher;
 use crate::pattern::Pattern;
 use std::io::Write;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 
+mod matcher;
 mod pattern;
 mod process;
+#[cfg(feature = "io_uring")]
+mod uring;
+mod watch;
+
+// caps how many directory handles, open files and in-flight zip reads may
+// be live at once, so a large tree can't exhaust the OS file-descriptor
+// limit; override with XMLQUERY_MAX_OPEN_FILES if the default doesn't fit.
+fn max_open_files() -> usize {
+    std::env::var("XMLQUERY_MAX_OPEN_FILES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get() * 64)
+                .unwrap_or(512)
+        })
+}
 
 #[tokio::main]
 async fn main() {
@@ -16,8 +36,34 @@ async fn main() {
     let Some(pattern) = args.next() else { return };
     let pattern = Box::leak(Box::new(Pattern::new(pattern.leak()))) as &'static Pattern<'static>;
 
-    // collect all the args into a vector for repeated use
-    let args = args.collect::<Vec<_>>();
+    // pull `--max-depth N`/`--include GLOB`/`--exclude GLOB`/`--archive
+    // GLOB`/`--watch` out of the remaining args, leaving only paths
+    let mut max_depth = usize::MAX;
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+    let mut archive = Vec::new();
+    let mut watch = false;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-depth" => {
+                let value = args.next().expect("--max-depth requires a value");
+                max_depth = value.parse().expect("--max-depth must be a non-negative integer");
+            }
+            "--include" => include.push(args.next().expect("--include requires a glob")),
+            "--exclude" => exclude.push(args.next().expect("--exclude requires a glob")),
+            "--archive" => archive.push(args.next().expect("--archive requires a glob")),
+            "--watch" => watch = true,
+            _ => positional.push(arg),
+        }
+    }
+    let args = positional;
+
+    // same selection logic governs enumeration and processing below
+    let matcher = Arc::new(Matcher::new(include, exclude, archive));
+
+    // shared budget of concurrently open directories/files/zip members
+    let permits = Arc::new(Semaphore::new(max_open_files()));
 
     // initialize total counter in an atomic shared heap allocated number
     let total = Arc::new(AtomicUsize::new(0));
@@ -26,10 +72,11 @@ async fn main() {
     let enumeration_start = Instant::now();
 
     // count all the paths to total
-    process::process_paths(&args, {
+    process::process_paths(&args, permits.clone(), matcher.clone(), max_depth, {
         let total = total.clone();
+        let matcher = matcher.clone();
         move |file| {
-            if file.path().ends_with(".xml") {
+            if matcher.is_match(&file.path()) {
                 total.fetch_add(1, Ordering::SeqCst);
             }
             async {}
@@ -46,25 +93,43 @@ async fn main() {
     // initialize the channel to deliver completed work to the output printer
     let (sender, mut receiver) = tokio::sync::mpsc::channel::<(String, String)>(64);
 
+    // spawn the watcher now (before `args` is consumed below) so it
+    // subscribes while the batch pass runs rather than after it; the batch
+    // pass does not wait for that subscription to finish. it holds its
+    // own clone of `sender`, so the output channel stays open for it even
+    // after the batch pass's clones are all dropped
+    let watch_task = watch.then(|| {
+        tokio::spawn(watch::watch_paths(
+            args.clone(),
+            matcher.clone(),
+            pattern,
+            max_depth,
+            permits.clone(),
+            sender.clone(),
+        ))
+    });
+
     // create the task that will process the files
-    let file_processor = process::process_paths(args, move |file| {
+    let file_processor = process::process_paths(args, permits, matcher.clone(), max_depth, move |file| {
         let sender = sender.clone();
         let path = file.path().to_owned();
-        if path.ends_with(".xml") {
-            match file.read_to_string() {
-                Ok(xml) => {
-                    // spawn tasks to do the heavy lifting
-                    // these tasks prevent the main function from returning because they hold senders
-                    // which are being reveived by the output_printer task which is joined on the main function
-                    tokio::task::spawn_blocking(move || {
-                        let output = parse_file(&xml, pattern);
-                        let _ = futures::executor::block_on(sender.send((path, output)));
-                    });
+        let matcher = matcher.clone();
+        async move {
+            if matcher.is_match(&path) {
+                match file.read_to_string().await {
+                    Ok(xml) => {
+                        // spawn tasks to do the heavy lifting
+                        // these tasks prevent the main function from returning because they hold senders
+                        // which are being reveived by the output_printer task which is joined on the main function
+                        tokio::task::spawn_blocking(move || {
+                            let output = parse_file(&xml, pattern);
+                            let _ = futures::executor::block_on(sender.send((path, output)));
+                        });
+                    }
+                    Err(()) => {}
                 }
-                Err(()) => {}
             }
         }
-        async {}
     });
 
     // create the task that will print the output and status to stdout and stderr
@@ -80,35 +145,51 @@ async fn main() {
                     last_info = now;
                     let elapsed_time = now - process_start;
                     let avg_time_per_iteration = elapsed_time / count as u32;
-    
+
                     let seconds_per_iteration = avg_time_per_iteration.as_secs_f64();
                     let iterations_per_second = if seconds_per_iteration == 0.0 {
                         0.0
                     } else {
                         1.0 / seconds_per_iteration
                     };
-    
-                    let remaining_iterations = total - count;
-                    let estimated_remaining_time = avg_time_per_iteration * remaining_iterations as u32;
-    
-                    eprintln!(
-                        "({} / {}) {}% - ELAPSED: {:.2?} - FPS: {:.0?} - ERT: {:.2?} {}",
-                        count,
-                        total,
-                        (count * 100) / total,
-                        elapsed_time,
-                        iterations_per_second,
-                        estimated_remaining_time,
-                        &path,
-                    );
+
+                    // `count` can run past `total` once `--watch` starts
+                    // feeding in reprocessed files after the one-time
+                    // enumeration pass, so the batch-pass percentage/ETA
+                    // no longer make sense past that point.
+                    if total > 0 && count <= total {
+                        let remaining_iterations = total - count;
+                        let estimated_remaining_time = avg_time_per_iteration * remaining_iterations as u32;
+
+                        eprintln!(
+                            "({} / {}) {}% - ELAPSED: {:.2?} - FPS: {:.0?} - ERT: {:.2?} {}",
+                            count,
+                            total,
+                            (count * 100) / total,
+                            elapsed_time,
+                            iterations_per_second,
+                            estimated_remaining_time,
+                            &path,
+                        );
+                    } else {
+                        eprintln!(
+                            "({}) watch - ELAPSED: {:.2?} - FPS: {:.0?} {}",
+                            count, elapsed_time, iterations_per_second, &path,
+                        );
+                    }
                 }
             }
             stdout_lock.write_all(output.as_bytes()).expect("stdout failure");
         }
     };
 
-    // execute both in paralel, wait for them to complete
-    tokio::join!(output_printer, file_processor);
+    // the printer must keep draining in the background: under `--watch` it
+    // never finishes (the watcher holds its own `sender` clone), so it
+    // can't be `tokio::join!`ed alongside `file_processor` without the
+    // exit-code check below becoming unreachable whenever `--watch` is set
+    let printer_task = tokio::spawn(output_printer);
+
+    let all_paths_existed = file_processor.await;
 
     let finished = Instant::now();
 
@@ -116,9 +197,20 @@ async fn main() {
     eprintln!("ENUMERATED - {:#?}", process_start - enumeration_start);
     eprintln!("PROCESSED  - {:#?}", finished - process_start);
     eprintln!("TOTAL      - {:#?}", finished - enumeration_start);
+
+    if !all_paths_existed {
+        std::process::exit(1);
+    }
+
+    // keep running: reprocess files as they change, and keep draining output
+    if let Some(watch_task) = watch_task {
+        let _ = tokio::join!(watch_task, printer_task);
+    } else {
+        let _ = printer_task.await;
+    }
 }
 
-fn parse_file(xml: &str, pattern: &Pattern) -> String {
+pub(crate) fn parse_file(xml: &str, pattern: &Pattern) -> String {
     let doc = roxmltree::Document::parse(&xml).unwrap();
     let table = print_by_pattern(&pattern, doc.root());
     let mut output = String::with_capacity(512);
@@ -0,0 +1,50 @@
+// archive containers we transparently look inside of when no `--archive`
+// glob is given; pass `--archive` (repeatable, same shape as `--include`)
+// to recognize other formats without a source change
+const DEFAULT_ARCHIVE_GLOBS: &[&str] = &["**/*.zip", "**/*.jar", "**/*.odt"];
+
+/// Governs both which paths get opened as archives and which paths get
+/// handed to the XML parser, so enumeration and processing always agree.
+pub struct Matcher {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+    archive: Vec<glob::Pattern>,
+}
+
+impl Matcher {
+    pub fn new(include: Vec<String>, exclude: Vec<String>, archive: Vec<String>) -> Self {
+        let include = if include.is_empty() {
+            vec!["**/*.xml".to_owned()]
+        } else {
+            include
+        };
+        let archive = if archive.is_empty() {
+            DEFAULT_ARCHIVE_GLOBS.iter().map(|pattern| (*pattern).to_owned()).collect()
+        } else {
+            archive
+        };
+        Matcher {
+            include: include
+                .iter()
+                .map(|pattern| glob::Pattern::new(pattern).expect("invalid --include glob"))
+                .collect(),
+            exclude: exclude
+                .iter()
+                .map(|pattern| glob::Pattern::new(pattern).expect("invalid --exclude glob"))
+                .collect(),
+            archive: archive
+                .iter()
+                .map(|pattern| glob::Pattern::new(pattern).expect("invalid --archive glob"))
+                .collect(),
+        }
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.include.iter().any(|pattern| pattern.matches(path))
+            && !self.exclude.iter().any(|pattern| pattern.matches(path))
+    }
+
+    pub fn is_archive(&self, path: &str) -> bool {
+        self.archive.iter().any(|pattern| pattern.matches(path))
+    }
+}
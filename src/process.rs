@@ -1,29 +1,46 @@
+use crate::matcher::Matcher;
+use async_zip::base::read::WithoutEntry;
+use async_zip::tokio::read::seek::ZipFileReader;
+use async_zip::tokio::read::ZipEntryReader;
 use either::Either;
 use futures::{future::join_all, FutureExt};
 use std::future::Future;
-use std::{fs::Metadata, io::Read};
-use zip::read::ZipFile;
+use std::sync::Arc;
+use std::fs::Metadata;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::sync::Semaphore;
+
+pub struct ZipMember<'a> {
+    name: String,
+    reader: ZipEntryReader<'a, File, WithoutEntry>,
+}
 
 pub struct ProcessedFile<'a> {
     path: &'a str,
-    meta: Either<Metadata, ZipFile<'a>>,
+    meta: Either<Metadata, ZipMember<'a>>,
 }
 
 impl<'a> ProcessedFile<'a> {
     pub fn path<'s, 'o: 'a + 's>(&'s self) -> String {
         match &self.meta {
             Either::Left(_) => self.path.to_owned(),
-            Either::Right(zipfile) => format!("{}/{}", self.path, zipfile.name()),
+            Either::Right(member) => format!("{}/{}", self.path, member.name),
         }
     }
-    pub fn read_to_string(self) -> Result<String, ()> {
+    pub async fn read_to_string(self) -> Result<String, ()> {
         match self.meta {
-            Either::Left(_) => std::fs::read_to_string(&self.path).map_err(catch_io(&self.path)),
-            Either::Right(mut zipfile) => {
-                let mut buf = String::with_capacity(zipfile.size() as usize);
-                zipfile
+            #[cfg(feature = "io_uring")]
+            Either::Left(_) => crate::uring::submit_read(self.path).await.map_err(catch_io(self.path)),
+            #[cfg(not(feature = "io_uring"))]
+            Either::Left(_) => tokio::fs::read_to_string(&self.path).await.map_err(catch_io(&self.path)),
+            Either::Right(mut member) => {
+                let mut buf = String::new();
+                member
+                    .reader
                     .read_to_string(&mut buf)
-                    .map_err(catch_io(zipfile.name()))?;
+                    .await
+                    .map_err(catch_io(&member.name))?;
                 Ok(buf)
             }
         }
@@ -32,51 +49,106 @@ impl<'a> ProcessedFile<'a> {
 
 pub async fn process_paths<F: Future<Output = ()>>(
     paths: impl IntoIterator<Item = impl AsRef<str>>,
+    permits: Arc<Semaphore>,
+    matcher: Arc<Matcher>,
+    max_depth: usize,
     callback: impl for<'a> Fn(ProcessedFile<'a>) -> F + Clone + Send + 'static,
-) {
-    join_all(paths.into_iter().map(|path| {
+) -> bool {
+    let results = join_all(paths.into_iter().map(|path| {
         let callback = callback.clone();
+        let permits = permits.clone();
+        let matcher = matcher.clone();
         async move {
-            process_path(path.as_ref().to_owned(), callback).await;
+            process_path(path.as_ref().to_owned(), permits, matcher, max_depth, callback).await
         }
     }))
     .await;
+    results.into_iter().all(|ok| ok)
 }
 
+// `path` always comes straight off the command line here: process_folder
+// recurses into process_folder/process_file directly, never back through
+// process_path, so a metadata failure below is always a bad top-level
+// argument rather than a transient error on some nested directory entry.
 pub async fn process_path<F: Future<Output = ()>>(
     path: String,
+    permits: Arc<Semaphore>,
+    matcher: Arc<Matcher>,
+    max_depth: usize,
     callback: impl for<'a> Fn(ProcessedFile<'a>) -> F + Clone + Send + 'static,
-) {
-    let Ok(metadata) = tokio::fs::metadata(&path).await.map_err(catch_io(&path)) else {
-        return;
+) -> bool {
+    let permit = permits
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("semaphore is never closed");
+    let metadata = tokio::fs::metadata(&path).await;
+    drop(permit);
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(error) => {
+            eprintln!("{path}: path does not exist ({error})");
+            return false;
+        }
     };
     if metadata.is_dir() {
-        process_folder(path, callback.clone()).await;
+        process_folder(path, permits, matcher, max_depth, callback.clone()).await;
     } else if metadata.is_file() {
-        process_file(path, metadata, callback.clone()).await;
+        process_file(path, metadata, permits, matcher, callback.clone()).await;
     } else {
         unreachable!()
     }
+    true
 }
 
 pub async fn process_folder<F: Future<Output = ()>>(
     path: String,
+    permits: Arc<Semaphore>,
+    matcher: Arc<Matcher>,
+    depth: usize,
     callback: impl for<'a> Fn(ProcessedFile<'a>) -> F + Clone + Send + 'static,
 ) {
     let _ = async {
-        let mut dir = tokio::fs::read_dir(&path).await?;
+        // held only across the directory listing itself, not across the
+        // recursion into children, so deep trees can't deadlock waiting
+        // on permits their own children are holding.
+        let entries = {
+            let permit = permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            let mut dir = tokio::fs::read_dir(&path).await?;
+            let mut entries = Vec::new();
+            while let Some(next) = dir.next_entry().await? {
+                let metadata = next.metadata().await?;
+                let name = next.file_name();
+                let Some(name) = name.to_str() else {
+                    continue;
+                };
+                entries.push((format!("{}/{}", path, name), metadata));
+            }
+            drop(permit);
+            entries
+        };
         let mut set = Vec::new();
-        while let Some(next) = dir.next_entry().await? {
-            let metadata = next.metadata().await?;
-            let name = next.file_name();
-            let Some(name) = name.to_str() else {
-                continue;
-            };
-            let path = format!("{}/{}", path, name);
+        for (path, metadata) in entries {
             if metadata.is_dir() {
-                set.push(process_folder(path, callback.clone()).left_future());
+                // depth 0 means "list this directory, don't recurse
+                // further", not "skip it", so a subdirectory only gets
+                // descended into once there's still budget left for it
+                if depth == 0 {
+                    continue;
+                }
+                set.push(
+                    process_folder(path, permits.clone(), matcher.clone(), depth - 1, callback.clone())
+                        .left_future(),
+                );
             } else if metadata.is_file() {
-                set.push(process_file(path, metadata, callback.clone()).right_future());
+                set.push(
+                    process_file(path, metadata, permits.clone(), matcher.clone(), callback.clone())
+                        .right_future(),
+                );
             } else {
                 unreachable!()
             }
@@ -91,32 +163,46 @@ pub async fn process_folder<F: Future<Output = ()>>(
 pub async fn process_file<F: Future<Output = ()>>(
     path: String,
     metadata: Metadata,
+    permits: Arc<Semaphore>,
+    matcher: Arc<Matcher>,
     callback: impl for<'a> Fn(ProcessedFile<'a>) -> F + Clone + Send + 'static,
 ) {
-    if path.ends_with(".zip") {
-        tokio::task::spawn_blocking(move || {
-            let _ = (|| {
-                let file = std::fs::File::open(&path)?;
-                let mut zip = zip::ZipArchive::new(file)?;
-                for i in 0..zip.len() {
-                    let zipfile = zip.by_index(i)?;
-                    futures::executor::block_on(callback(ProcessedFile {
-                        path: &path,
-                        meta: Either::Right(zipfile),
-                    }));
-                }
-                Ok(())
-            })()
-            .map_err(catch_io(&path));
-        })
-        .await
-        .unwrap();
+    if matcher.is_archive(&path) {
+        let permit = permits
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let result: std::io::Result<()> = async {
+            let file = File::open(&path).await?;
+            let mut zip = ZipFileReader::with_tokio(file).await.map_err(zip_to_io)?;
+            for index in 0..zip.file().entries().len() {
+                let Ok(name) = zip.file().entries()[index].filename().as_str() else {
+                    continue;
+                };
+                let name = name.to_owned();
+                let reader = zip.reader_without_entry(index).await.map_err(zip_to_io)?;
+                callback(ProcessedFile {
+                    path: &path,
+                    meta: Either::Right(ZipMember { name, reader }),
+                })
+                .await;
+            }
+            Ok(())
+        }
+        .await;
+        drop(permit);
+        let _ = result.map_err(catch_io(&path));
     } else {
+        let permit = permits
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
         callback(ProcessedFile {
             path: &path,
             meta: Either::Left(metadata),
         })
-        .await
+        .await;
+        drop(permit);
     }
 }
 
@@ -125,3 +211,7 @@ const fn catch_io<'a>(path: &'a str) -> impl FnOnce(std::io::Error) + 'a {
         eprintln!("{path}: {error:#?}");
     }
 }
+
+fn zip_to_io(error: async_zip::error::ZipError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, error)
+}
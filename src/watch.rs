@@ -0,0 +1,107 @@
+use crate::matcher::Matcher;
+use crate::parse_file;
+use crate::pattern::Pattern;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Semaphore;
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Keeps `paths` under watch after the initial batch pass, re-parsing any
+/// matching file as it is created or modified and feeding the result into
+/// the same output channel the batch pass used, so it prints identically.
+pub async fn watch_paths(
+    paths: Vec<String>,
+    matcher: Arc<Matcher>,
+    pattern: &'static Pattern<'static>,
+    max_depth: usize,
+    permits: Arc<Semaphore>,
+    sender: Sender<(String, String)>,
+) {
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let _ = events_tx.send(event);
+    }) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            eprintln!("watch: {error:#?}");
+            return;
+        }
+    };
+    for path in &paths {
+        if let Err(error) = watcher.watch(Path::new(path), RecursiveMode::Recursive) {
+            eprintln!("{path}: {error:#?}");
+        }
+    }
+
+    // coalesce bursts of events per path within DEBOUNCE before reprocessing
+    let mut pending: HashMap<String, Instant> = HashMap::new();
+    loop {
+        let timeout = pending
+            .values()
+            .map(|seen| DEBOUNCE.saturating_sub(seen.elapsed()))
+            .min()
+            .unwrap_or(DEBOUNCE);
+        tokio::select! {
+            event = events_rx.recv() => {
+                let Some(event) = event else { break };
+                let Ok(event) = event else { continue };
+                for path in event.paths {
+                    let Some(path) = path.to_str() else { continue };
+                    pending.insert(path.to_owned(), Instant::now());
+                }
+            }
+            _ = tokio::time::sleep(timeout), if !pending.is_empty() => {}
+        }
+
+        let ready = pending
+            .iter()
+            .filter(|(_, seen)| seen.elapsed() >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        for path in ready {
+            pending.remove(&path);
+            if !matcher.is_match(&path) || exceeds_max_depth(&path, &paths, max_depth) {
+                continue;
+            }
+            // same fd budget the batch pass reads through, so a burst of
+            // watch events can't open unboundedly many files at once
+            let permit = permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            #[cfg(feature = "io_uring")]
+            let xml = crate::uring::submit_read(&path).await;
+            #[cfg(not(feature = "io_uring"))]
+            let xml = tokio::fs::read_to_string(&path).await;
+            drop(permit);
+            let Ok(xml) = xml else {
+                continue;
+            };
+            let sender = sender.clone();
+            tokio::task::spawn_blocking(move || {
+                let output = parse_file(&xml, pattern);
+                let _ = futures::executor::block_on(sender.send((path, output)));
+            });
+        }
+    }
+}
+
+// mirrors process_folder's recursion budget so --watch can't surface files
+// the batch pass's --max-depth would have skipped: a file directly inside
+// one of `roots` is depth 0, one subdirectory down is depth 1, and so on.
+// a path that isn't under any watched root (shouldn't happen in practice)
+// is let through rather than guessed at.
+fn exceeds_max_depth(path: &str, roots: &[String], max_depth: usize) -> bool {
+    roots
+        .iter()
+        .filter_map(|root| path.strip_prefix(root.as_str()))
+        .map(|rest| rest.trim_start_matches('/').matches('/').count())
+        .min()
+        .map_or(false, |depth| depth > max_depth)
+}